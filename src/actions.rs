@@ -0,0 +1,152 @@
+//! Named permission actions, layered on top of the raw `u64` access mask.
+//!
+//! `SecurityDescriptor`/`check` keep dealing in bitmasks internally for
+//! speed; an [`ActionRegistry`] is just a name <-> bit-position table so
+//! callers don't have to hand-maintain bit constants.
+
+use crate::{CheckResult, Effect};
+use std::collections::HashMap;
+use std::fmt;
+
+/// An action name was requested that was never registered.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnknownAction(pub String);
+
+impl fmt::Display for UnknownAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown action '{}'", self.0)
+    }
+}
+
+impl std::error::Error for UnknownAction {}
+
+/// Maps symbolic action names (`"read"`, `"write"`, `"admin"`, ...) to
+/// individual bits of the `u64` access mask.
+#[derive(Debug, Clone, Default)]
+pub struct ActionRegistry {
+    names: Vec<String>,
+    bits: HashMap<String, u32>,
+}
+
+impl ActionRegistry {
+    pub fn new() -> ActionRegistry {
+        ActionRegistry {
+            names: Vec::new(),
+            bits: HashMap::new(),
+        }
+    }
+
+    /// Registers `name`, returning the bit position assigned to it.
+    /// Registering the same name again returns its existing bit.
+    ///
+    /// # Panics
+    /// Panics if this would register more than 64 distinct actions.
+    pub fn add_action(&mut self, name: &str) -> u32 {
+        if let Some(&bit) = self.bits.get(name) {
+            return bit;
+        }
+        let bit = self.names.len() as u32;
+        assert!(bit < 64, "ActionRegistry supports at most 64 actions");
+        self.names.push(name.to_string());
+        self.bits.insert(name.to_string(), bit);
+        bit
+    }
+
+    /// Builds a mask out of a list of action names.
+    pub fn mask(&self, names: &[&str]) -> Result<u64, UnknownAction> {
+        let mut mask = 0u64;
+        for name in names {
+            let bit = self
+                .bits
+                .get(*name)
+                .ok_or_else(|| UnknownAction((*name).to_string()))?;
+            mask |= 1u64 << bit;
+        }
+        Ok(mask)
+    }
+
+    /// Decodes a mask back into the set of action names it grants. Bits that
+    /// don't correspond to a registered action are silently ignored.
+    pub fn decode(&self, mask: u64) -> Vec<String> {
+        self.names
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| mask & (1u64 << bit) != 0)
+            .map(|(_, name)| name.clone())
+            .collect()
+    }
+
+    /// Decodes a [`CheckResult`], returning the granted action names for
+    /// `Allow`, or an empty list for anything else.
+    pub fn decode_result(&self, result: CheckResult) -> Vec<String> {
+        match result {
+            CheckResult::Allow(mask) => self.decode(mask),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Builds an `Effect::Allow` that grants exactly the named actions.
+    pub fn allow(&self, names: &[&str]) -> Result<Effect, UnknownAction> {
+        Ok(Effect::Allow(Some(self.mask(names)?)))
+    }
+
+    /// Builds an `Effect::Next` that narrows access down to the named actions.
+    pub fn next(&self, names: &[&str]) -> Result<Effect, UnknownAction> {
+        Ok(Effect::Next(Some(self.mask(names)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{access, AccessToken, Entry, Item, Object, Prefix, RuleSubject, SecurityDescriptor};
+
+    #[test]
+    fn mask_round_trips_through_decode() {
+        let mut registry = ActionRegistry::new();
+        registry.add_action("read");
+        registry.add_action("write");
+        registry.add_action("admin");
+
+        let mask = registry.mask(&["read", "admin"]).unwrap();
+        let mut granted = registry.decode(mask);
+        granted.sort();
+        assert_eq!(granted, vec!["admin".to_string(), "read".to_string()]);
+    }
+
+    #[test]
+    fn mask_rejects_unknown_action() {
+        let registry = ActionRegistry::new();
+        assert_eq!(
+            registry.mask(&["fly"]).unwrap_err(),
+            UnknownAction("fly".to_string())
+        );
+    }
+
+    #[test]
+    fn entries_authored_against_action_names() {
+        let mut registry = ActionRegistry::new();
+        registry.add_action("read");
+        registry.add_action("write");
+
+        let object = Object {
+            security: SecurityDescriptor {
+                acl: vec![Entry {
+                    subject: RuleSubject::Everyone,
+                    effect: registry.allow(&["read"]).unwrap(),
+                }],
+            },
+        };
+        let mut root = Prefix::new();
+        root.add_item("doc", Item::Object(object));
+
+        let anyone = AccessToken {
+            name: "anyone",
+            groups: &[],
+            roles: &[],
+        };
+        let requested = registry.mask(&["read", "write"]).unwrap();
+        let result = access(&root, anyone, &["doc"], requested, None);
+        assert_eq!(registry.decode_result(result), vec!["read".to_string()]);
+    }
+}
@@ -0,0 +1,266 @@
+//! Human-editable text representation of ACLs.
+//!
+//! A [`SecurityDescriptor`] round-trips through a line like
+//! `user:joe allow 0x6` (subject, effect, optional hex access mask), and a
+//! whole [`Prefix`] tree can be loaded from `path/to/object = <ruleset>`
+//! stanzas via [`Prefix::from_rules`].
+
+use crate::{Effect, Entry, Item, Object, Prefix, RuleSubject, SecurityDescriptor};
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+/// A failure to parse the text ACL format, carrying the 1-based line number
+/// of the offending line so it can be reported back to whoever edited the file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for RuleSubject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleSubject::User(name) => write!(f, "user:{}", name),
+            RuleSubject::Group(name) => write!(f, "group:{}", name),
+            RuleSubject::Role(name) => write!(f, "role:{}", name),
+            RuleSubject::Everyone => write!(f, "everyone"),
+        }
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Effect::Allow(None) => write!(f, "allow"),
+            Effect::Allow(Some(mask)) => write!(f, "allow 0x{:x}", mask),
+            Effect::Deny => write!(f, "deny"),
+            Effect::Next(None) => write!(f, "next"),
+            Effect::Next(Some(mask)) => write!(f, "next 0x{:x}", mask),
+        }
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.subject, self.effect)
+    }
+}
+
+impl fmt::Display for SecurityDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, entry) in self.acl.iter().enumerate() {
+            if idx > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_subject(token: &str) -> Result<RuleSubject, String> {
+    if token == "everyone" {
+        return Ok(RuleSubject::Everyone);
+    }
+    let mut parts = token.splitn(2, ':');
+    let kind = parts.next().unwrap();
+    let name = parts.next().ok_or_else(|| {
+        format!(
+            "subject '{}' must be 'user:<name>', 'group:<name>', 'role:<name>' or 'everyone'",
+            token
+        )
+    })?;
+    match kind {
+        "user" => Ok(RuleSubject::User(name.to_string())),
+        "group" => Ok(RuleSubject::Group(name.to_string())),
+        "role" => Ok(RuleSubject::Role(name.to_string())),
+        other => Err(format!("unknown subject kind '{}'", other)),
+    }
+}
+
+fn parse_mask(token: &str) -> Result<u64, String> {
+    let digits = token
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("access mask '{}' must be hex, e.g. 0x6", token))?;
+    u64::from_str_radix(digits, 16).map_err(|e| format!("invalid hex mask '{}': {}", token, e))
+}
+
+fn parse_entry(s: &str) -> Result<Entry, String> {
+    let mut tokens = s.split_whitespace();
+    let subject_tok = tokens.next().ok_or("missing subject")?;
+    let effect_tok = tokens
+        .next()
+        .ok_or("missing effect, expected allow/deny/next")?;
+    let mask_tok = tokens.next();
+    if tokens.next().is_some() {
+        return Err(format!("unexpected trailing tokens in '{}'", s));
+    }
+
+    let subject = parse_subject(subject_tok)?;
+    let mask = mask_tok.map(parse_mask).transpose()?;
+    let effect = match effect_tok {
+        "allow" => Effect::Allow(mask),
+        "next" => Effect::Next(mask),
+        "deny" => {
+            if mask.is_some() {
+                return Err("'deny' does not take an access mask".to_string());
+            }
+            Effect::Deny
+        }
+        other => return Err(format!("unknown effect '{}', expected allow/deny/next", other)),
+    };
+    Ok(Entry { subject, effect })
+}
+
+impl FromStr for SecurityDescriptor {
+    type Err = ParseError;
+
+    /// Parses entries separated by newlines and/or `;`, e.g.
+    /// `user:joe allow 0x6; group:admin next 0xF` or one entry per line.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut acl = Vec::new();
+        for (idx, line) in s.split('\n').enumerate() {
+            for raw in line.split(';') {
+                let trimmed = raw.trim();
+                if trimmed.is_empty() {
+                    continue;
+                }
+                let entry = parse_entry(trimmed).map_err(|reason| ParseError {
+                    line: idx + 1,
+                    reason,
+                })?;
+                acl.push(entry);
+            }
+        }
+        Ok(SecurityDescriptor { acl })
+    }
+}
+
+impl Prefix {
+    /// Loads a whole tree from `path/to/object = <ruleset>` stanzas, one per
+    /// line (blank lines and `#`-comments are skipped). Intermediate path
+    /// segments become [`Prefix`] nodes automatically; the final segment
+    /// becomes an [`Object`] carrying the parsed [`SecurityDescriptor`].
+    pub fn from_rules<R: io::Read>(reader: R) -> Result<Prefix, ParseError> {
+        let reader = io::BufReader::new(reader);
+        let mut root = Prefix::new();
+        for (idx, line) in reader.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = line.map_err(|e| ParseError {
+                line: line_no,
+                reason: e.to_string(),
+            })?;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let mut parts = trimmed.splitn(2, '=');
+            let path = parts.next().unwrap().trim();
+            let ruleset = parts
+                .next()
+                .ok_or_else(|| ParseError {
+                    line: line_no,
+                    reason: "expected 'path = ruleset'".to_string(),
+                })?
+                .trim();
+            let security: SecurityDescriptor = ruleset.parse().map_err(|e: ParseError| ParseError {
+                line: line_no,
+                reason: e.reason,
+            })?;
+
+            let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+            let (leaf, parents) = segments.split_last().ok_or_else(|| ParseError {
+                line: line_no,
+                reason: "empty path".to_string(),
+            })?;
+
+            let mut cur = &mut root;
+            for segment in parents {
+                cur = match cur
+                    .items
+                    .entry((*segment).to_string())
+                    .or_insert_with(|| Item::Prefix(Prefix::new()))
+                {
+                    Item::Prefix(p) => p,
+                    Item::Object(_) => {
+                        return Err(ParseError {
+                            line: line_no,
+                            reason: format!("'{}' is already an object, not a prefix", segment),
+                        })
+                    }
+                };
+            }
+            if let Some(Item::Prefix(_)) = cur.items.get(*leaf) {
+                return Err(ParseError {
+                    line: line_no,
+                    reason: format!("'{}' is already a prefix, not an object", leaf),
+                });
+            }
+            cur.items
+                .insert((*leaf).to_string(), Item::Object(Object { security }));
+        }
+        Ok(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{access, AccessToken, CheckResult};
+
+    #[test]
+    fn parses_and_round_trips() {
+        let text = "user:joe allow 0x6\ngroup:admin next 0xf\neveryone deny";
+        let sec: SecurityDescriptor = text.parse().unwrap();
+        assert_eq!(sec.acl.len(), 3);
+        assert_eq!(sec.to_string(), "user:joe allow 0x6; group:admin next 0xf; everyone deny");
+        let reparsed: SecurityDescriptor = sec.to_string().parse().unwrap();
+        assert_eq!(reparsed.to_string(), sec.to_string());
+    }
+
+    #[test]
+    fn reports_line_number_on_bad_effect() {
+        let text = "everyone allow\nuser:joe maybe";
+        let err = text.parse::<SecurityDescriptor>().unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn from_rules_builds_tree() {
+        let text = "\
+# comment
+repos/acl = group:admin allow
+repos/private = everyone deny
+";
+        let root = Prefix::from_rules(text.as_bytes()).unwrap();
+        let token = AccessToken {
+            name: "joe",
+            groups: &["admin".to_string()],
+            roles: &[],
+        };
+        let res = access(&root, token, &["repos", "acl"], 0, None);
+        assert_eq!(res, CheckResult::Allow(0));
+        let res = access(&root, token, &["repos", "private"], 0, None);
+        assert_eq!(res, CheckResult::Deny);
+    }
+
+    #[test]
+    fn from_rules_rejects_object_overwriting_existing_prefix() {
+        let text = "\
+a/b = everyone allow
+a = everyone deny
+";
+        let err = Prefix::from_rules(text.as_bytes()).unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.reason, "'a' is already a prefix, not an object");
+    }
+}
@@ -1,36 +1,98 @@
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
-#[derive(Debug, Clone)]
+mod actions;
+mod format;
+mod persist;
+pub use actions::{ActionRegistry, UnknownAction};
+pub use format::ParseError;
+pub use persist::{AclStore, FileAclStore, PersistError};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Effect {
     Allow(Option<u64>),
     Deny,
     Next(Option<u64>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RuleSubject {
     User(String),
     Group(String),
     Everyone,
+    Role(String),
 }
 
-#[derive(Debug, Clone)]
+/// A named bundle of groups, optionally inheriting from other roles.
+///
+/// Roles form a graph (`parents` are edges to other role names), so a role
+/// can grant everything its parents grant without repeating their groups.
+#[derive(Debug, Clone, Default)]
+pub struct Role {
+    pub parents: Vec<String>,
+    pub groups: Vec<String>,
+}
+
+/// Maps role names to their definitions, used to resolve [`RuleSubject::Role`]
+/// and role-bundled groups against a token's assigned roles.
+#[derive(Debug, Clone, Default)]
+pub struct RoleRegistry {
+    roles: HashMap<String, Role>,
+}
+
+impl RoleRegistry {
+    pub fn new() -> RoleRegistry {
+        RoleRegistry {
+            roles: HashMap::new(),
+        }
+    }
+
+    pub fn add_role(&mut self, name: &str, role: Role) {
+        self.roles.insert(name.to_string(), role);
+    }
+
+    /// All role names reachable from `start` by walking `parents` edges,
+    /// including `start` itself. Cycle-safe: each name is visited once.
+    fn reachable_roles(&self, start: &[String]) -> HashSet<String> {
+        let mut visited = HashSet::new();
+        let mut stack: Vec<String> = start.to_vec();
+        while let Some(name) = stack.pop() {
+            if !visited.insert(name.clone()) {
+                continue;
+            }
+            if let Some(role) = self.roles.get(&name) {
+                stack.extend(role.parents.iter().cloned());
+            }
+        }
+        visited
+    }
+
+    /// Whether a role reachable from `start` bundles `group`.
+    fn grants_group(&self, start: &[String], group: &str) -> bool {
+        self.reachable_roles(start)
+            .iter()
+            .filter_map(|name| self.roles.get(name))
+            .any(|role| role.groups.iter().any(|g| g == group))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entry {
     pub subject: RuleSubject,
     pub effect: Effect,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityDescriptor {
     pub acl: Vec<Entry>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Object {
     pub security: SecurityDescriptor,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Item {
     Object(Object),
     Prefix(Prefix),
@@ -41,12 +103,14 @@ pub enum Item {
 pub struct AccessToken<'a> {
     name: &'a str,
     groups: &'a [String],
+    roles: &'a [String],
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Prefix {
     self_security: SecurityDescriptor,
     items: HashMap<String, Item>,
+    patterns: Vec<(String, Item)>,
 }
 
 impl Default for Prefix {
@@ -55,11 +119,54 @@ impl Default for Prefix {
     }
 }
 
+/// Counts the non-`*` characters in a glob, used to rank pattern items by
+/// specificity: more literal characters wins.
+fn glob_literal_len(glob: &str) -> usize {
+    glob.chars().filter(|&c| c != '*').count()
+}
+
+/// Minimal glob matching: `*` matches any (possibly empty) run of
+/// characters, any number of times; there is no other metacharacter.
+fn glob_match(glob: &str, text: &str) -> bool {
+    let parts: Vec<&str> = glob.split('*').collect();
+    if parts.len() == 1 {
+        return glob == text;
+    }
+
+    let mut rest = text;
+    if let Some(first) = parts.first() {
+        if !first.is_empty() {
+            if !rest.starts_with(first) {
+                return false;
+            }
+            rest = &rest[first.len()..];
+        }
+    }
+    let last = parts.last().unwrap();
+    if !last.is_empty() {
+        if !rest.ends_with(last) {
+            return false;
+        }
+        rest = &rest[..rest.len() - last.len()];
+    }
+    for part in &parts[1..parts.len() - 1] {
+        if part.is_empty() {
+            continue;
+        }
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
 impl Prefix {
     pub fn new() -> Prefix {
         Prefix {
             self_security: SecurityDescriptor::allow_all(),
             items: HashMap::new(),
+            patterns: Vec::new(),
         }
     }
 
@@ -67,6 +174,7 @@ impl Prefix {
         Prefix {
             self_security: sec,
             items: HashMap::new(),
+            patterns: Vec::new(),
         }
     }
 
@@ -78,21 +186,53 @@ impl Prefix {
         self.items.insert(name.to_string(), item.clone());
     }
 
+    /// Registers `item` under a glob (e.g. `"repo-*"` or `"*"`) so that any
+    /// segment not matched by an exact [`Prefix::add_item`] name can still
+    /// resolve to it. When several registered globs match the same segment,
+    /// [`Prefix::get_item`] picks the one with the most literal (non-`*`)
+    /// characters, breaking ties by glob text so lookups stay deterministic.
+    pub fn add_pattern_item(&mut self, glob: &str, item: Item) {
+        self.patterns.push((glob.to_string(), item));
+    }
+
     fn self_security(&self) -> &SecurityDescriptor {
         &self.self_security
     }
 
-    fn get_item(&self, item_name: &str) -> Option<&Item> {
+    fn get_item_exact(&self, item_name: &str) -> Option<&Item> {
         self.items.get(item_name)
     }
+
+    /// Exact lookup first; on a miss, the most specific matching pattern.
+    fn get_item(&self, item_name: &str) -> Option<&Item> {
+        if let Some(item) = self.get_item_exact(item_name) {
+            return Some(item);
+        }
+        self.patterns
+            .iter()
+            .filter(|(glob, _)| glob_match(glob, item_name))
+            .max_by_key(|(glob, _)| (glob_literal_len(glob), std::cmp::Reverse(glob.clone())))
+            .map(|(_, item)| item)
+    }
 }
 
 impl RuleSubject {
-    fn covers(&self, token: AccessToken) -> bool {
+    fn covers(&self, token: AccessToken, roles: Option<&RoleRegistry>) -> bool {
         match self {
             RuleSubject::User(ref login) => token.name == login,
-            RuleSubject::Group(ref group) => token.groups.contains(&group),
+            RuleSubject::Group(ref group) => {
+                token.groups.contains(group)
+                    || roles
+                        .map(|r| r.grants_group(token.roles, group))
+                        .unwrap_or(false)
+            }
             RuleSubject::Everyone => true,
+            RuleSubject::Role(ref role) => {
+                token.roles.contains(role)
+                    || roles
+                        .map(|r| r.reachable_roles(token.roles).contains(role))
+                        .unwrap_or(false)
+            }
         }
     }
 }
@@ -156,12 +296,37 @@ impl SecurityDescriptor {
         *cur &= next;
     }
 
-    fn check(&self, token: AccessToken, requested_access: u64) -> CheckResult {
+    fn check(
+        &self,
+        token: AccessToken,
+        requested_access: u64,
+        roles: Option<&RoleRegistry>,
+    ) -> CheckResult {
+        self.check_traced(token, requested_access, roles, &mut None)
+    }
+
+    /// Same as [`SecurityDescriptor::check`], additionally recording every
+    /// entry that matched `token` (in evaluation order) into `trace`, when
+    /// given.
+    fn check_traced(
+        &self,
+        token: AccessToken,
+        requested_access: u64,
+        roles: Option<&RoleRegistry>,
+        trace: &mut Option<Vec<MatchedEntry>>,
+    ) -> CheckResult {
         let mut provided_access = requested_access;
-        for entry in &self.acl {
-            if !entry.subject.covers(token) {
+        for (index, entry) in self.acl.iter().enumerate() {
+            if !entry.subject.covers(token, roles) {
                 continue;
             }
+            if let Some(trace) = trace.as_mut() {
+                trace.push(MatchedEntry {
+                    index,
+                    subject: entry.subject.clone(),
+                    effect: entry.effect.clone(),
+                });
+            }
             match &entry.effect {
                 Effect::Allow(next) => {
                     Self::update_access(&mut provided_access, *next);
@@ -203,56 +368,118 @@ impl<'a> From<&'a Item> for ItemRef<'a> {
     }
 }
 
+/// A single ACL entry that matched a token while evaluating a
+/// [`SecurityDescriptor`], recorded for [`access_explained`].
+#[derive(Debug, Clone)]
+pub struct MatchedEntry {
+    pub index: usize,
+    pub subject: RuleSubject,
+    pub effect: Effect,
+}
+
+/// One step of the decision trace produced by [`access_explained`], in the
+/// order it was evaluated.
+#[derive(Debug, Clone)]
+pub enum TraceStep {
+    /// A prefix's own `self_security` was checked while descending into
+    /// `segment`.
+    PrefixCheck {
+        segment: String,
+        matched: Vec<MatchedEntry>,
+        access_before: u64,
+        access_after: u64,
+        result: CheckResult,
+    },
+    /// A `$ACL.Sudo` entry was present in the prefix and was evaluated.
+    SudoCheck { segment: String, granted: bool },
+    /// The leaf object's security descriptor was checked.
+    ObjectCheck {
+        matched: Vec<MatchedEntry>,
+        result: CheckResult,
+    },
+}
+
 /// If such an object exists in some prefix, and user has access to this object,
 /// then this user has full access to prefix content
 /// Access flags are ignored
 pub const SPECIAL_SEGMENT_SUDO: &str = "$ACL.Sudo";
 
+/// Walks `path` from `root`, narrowing `requested_access` at each prefix and
+/// applying the leaf object's ACL, per [`SecurityDescriptor::check`] and the
+/// `$ACL.Sudo` short-circuit. See [`access_explained`] for a version that
+/// also returns a decision trace; this is just that with the trace discarded,
+/// so the two can never drift apart.
 pub fn access<'a>(
     root: &Prefix,
     token: AccessToken,
     path: &'a [&'a str],
     requested_access: u64,
+    roles: Option<&RoleRegistry>,
 ) -> CheckResult {
+    access_explained(root, token, path, requested_access, roles).0
+}
+
+/// Same as [`access`], but additionally returns an ordered [`TraceStep`] log
+/// describing exactly how the decision was reached, suitable for an audit
+/// log or for debugging why a check was denied.
+pub fn access_explained<'a>(
+    root: &Prefix,
+    token: AccessToken,
+    path: &'a [&'a str],
+    requested_access: u64,
+    roles: Option<&RoleRegistry>,
+) -> (CheckResult, Vec<TraceStep>) {
+    let mut trace = Vec::new();
     let mut cur_item = ItemRef::Prefix(root);
     let mut cur_access = requested_access;
     for &segment in path {
         let cur_prefix = match cur_item {
             ItemRef::Prefix(pref) => pref,
-            ItemRef::Object(_obj) => return CheckResult::NotFound,
+            ItemRef::Object(_obj) => return (CheckResult::NotFound, trace),
         };
-        {
-            let check_res = cur_prefix.self_security().check(token, cur_access);
-            match check_res {
-                CheckResult::Allow(acc) => {
-                    cur_access &= acc;
-                }
-                CheckResult::Deny => {
-                    return CheckResult::Deny;
-                }
-                CheckResult::NotFound => unreachable!(),
-                CheckResult::NoMatch => return CheckResult::NoMatch,
+
+        let access_before = cur_access;
+        let mut matched = Some(Vec::new());
+        let check_res = cur_prefix
+            .self_security()
+            .check_traced(token, cur_access, roles, &mut matched);
+        match check_res {
+            CheckResult::Allow(acc) => cur_access &= acc,
+            CheckResult::NotFound => unreachable!(),
+            CheckResult::Deny | CheckResult::NoMatch => {
+                trace.push(TraceStep::PrefixCheck {
+                    segment: segment.to_string(),
+                    matched: matched.unwrap(),
+                    access_before,
+                    access_after: cur_access,
+                    result: check_res,
+                });
+                return (check_res, trace);
             }
         }
-        // }
-        match cur_prefix.get_item(SPECIAL_SEGMENT_SUDO) {
-            None => {}
-            Some(item) => {
-                let item: ItemRef = item.into();
-                let obj = item.as_object().unwrap();
-
-                let check_res = obj.security.check(token, 0);
-                if let CheckResult::Allow(_) = check_res {
-                    // no more lookup
-                    // sudo granted
-                    return CheckResult::Allow(cur_access);
-                }
+        trace.push(TraceStep::PrefixCheck {
+            segment: segment.to_string(),
+            matched: matched.unwrap(),
+            access_before,
+            access_after: cur_access,
+            result: check_res,
+        });
+
+        if let Some(item) = cur_prefix.get_item_exact(SPECIAL_SEGMENT_SUDO) {
+            let item: ItemRef = item.into();
+            let obj = item.as_object().unwrap();
+            let granted = matches!(obj.security.check(token, 0, roles), CheckResult::Allow(_));
+            trace.push(TraceStep::SudoCheck {
+                segment: segment.to_string(),
+                granted,
+            });
+            if granted {
+                return (CheckResult::Allow(cur_access), trace);
             }
-        };
+        }
+
         match cur_prefix.get_item(segment) {
-            None => {
-                return CheckResult::NotFound;
-            }
+            None => return (CheckResult::NotFound, trace),
             Some(item) => {
                 cur_item = match item {
                     Item::Prefix(pref) => ItemRef::Prefix(pref),
@@ -262,10 +489,18 @@ pub fn access<'a>(
         }
     }
     let obj = match cur_item {
-        ItemRef::Prefix(_p) => return CheckResult::NotFound,
+        ItemRef::Prefix(_p) => return (CheckResult::NotFound, trace),
         ItemRef::Object(obj) => obj,
     };
-    obj.security.check(token, cur_access)
+    let mut matched = Some(Vec::new());
+    let result = obj
+        .security
+        .check_traced(token, cur_access, roles, &mut matched);
+    trace.push(TraceStep::ObjectCheck {
+        matched: matched.unwrap(),
+        result,
+    });
+    (result, trace)
 }
 
 #[cfg(test)]
@@ -303,24 +538,32 @@ mod tests {
         let joe_admin = AccessToken {
             name: "joe",
             groups: &[s!("admin"), s!("jojo-fan")],
+            roles: &[],
         };
 
         let bob_hacker = AccessToken {
             name: "bob",
             groups: &[s!("jojo-fan")],
+            roles: &[],
         };
 
         let path = &["top-secret"];
 
-        let joe_access = access(&root, joe_admin, path, 0);
+        let joe_access = access(&root, joe_admin, path, 0, None);
         assert_eq!(joe_access, CheckResult::Allow(0));
-        let bob_access = access(&root, bob_hacker, path, 0);
+        let bob_access = access(&root, bob_hacker, path, 0, None);
         assert_eq!(bob_access, CheckResult::Deny);
     }
 
     #[test]
     fn access_crop() {
-        let root_security = SecurityDescriptor::with_capped_access(5);
+        let mut actions = ActionRegistry::new();
+        actions.add_action("read");
+        actions.add_action("write");
+        actions.add_action("execute");
+
+        let root_security =
+            SecurityDescriptor::with_capped_access(actions.mask(&["read", "execute"]).unwrap());
         let mut root = Prefix::with_security(root_security);
         root.self_security.add_entry(Entry {
             subject: RuleSubject::Everyone,
@@ -330,7 +573,7 @@ mod tests {
         {
             let entry = Entry {
                 subject: RuleSubject::Group("admin".to_string()),
-                effect: Effect::Allow(Some(6)),
+                effect: actions.allow(&["write", "execute"]).unwrap(),
             };
 
             object.add_entry(entry);
@@ -349,12 +592,17 @@ mod tests {
         let joe_admin = AccessToken {
             name: "joe",
             groups: &[s!("admin"), s!("jojo-fan")],
+            roles: &[],
         };
 
         let path = &["top-secret"];
 
-        let joe_access = access(&root, joe_admin, path, 255);
-        assert_eq!(joe_access, CheckResult::Allow(4));
+        let requested = actions.mask(&["read", "write", "execute"]).unwrap();
+        let joe_access = access(&root, joe_admin, path, requested, None);
+        assert_eq!(
+            actions.decode_result(joe_access),
+            vec!["execute".to_string()]
+        );
     }
 
     #[test]
@@ -391,17 +639,194 @@ mod tests {
         let jon_snow = AccessToken {
             name: "jon_snow",
             groups: &[],
+            roles: &[],
         };
 
         let cersei = AccessToken {
             name: "cersei",
             groups: &[],
+            roles: &[],
         };
 
-        let jon_access = access(&root, jon_snow, path, 179);
+        let jon_access = access(&root, jon_snow, path, 179, None);
         assert_eq!(jon_access, CheckResult::Allow(179));
 
-        let cersei_access = access(&root, cersei, path, 179);
+        let cersei_access = access(&root, cersei, path, 179, None);
         assert_eq!(cersei_access, CheckResult::Deny);
     }
+
+    #[test]
+    fn role_transitive_inheritance() {
+        let mut registry = RoleRegistry::new();
+        registry.add_role(
+            "junior-dev",
+            Role {
+                parents: vec![],
+                groups: vec![s!("readers")],
+            },
+        );
+        registry.add_role(
+            "senior-dev",
+            Role {
+                parents: vec![s!("junior-dev")],
+                groups: vec![s!("writers")],
+            },
+        );
+        // cyclic edge back to itself must not cause infinite recursion
+        registry.add_role(
+            "lead-dev",
+            Role {
+                parents: vec![s!("senior-dev"), s!("lead-dev")],
+                groups: vec![],
+            },
+        );
+
+        let mut object = SecurityDescriptor::empty();
+        object.add_entry(Entry {
+            subject: RuleSubject::Role(s!("senior-dev")),
+            effect: Effect::Allow(None),
+        });
+        object.add_entry(Entry {
+            subject: RuleSubject::Everyone,
+            effect: Effect::Deny,
+        });
+        let object = Object { security: object };
+
+        let mut root = Prefix::new();
+        root.add_item("repo", Item::Object(object));
+        let path = &["repo"];
+
+        let lead = AccessToken {
+            name: "alice",
+            groups: &[],
+            roles: &[s!("lead-dev")],
+        };
+        let junior = AccessToken {
+            name: "bob",
+            groups: &[],
+            roles: &[s!("junior-dev")],
+        };
+        let nobody = AccessToken {
+            name: "eve",
+            groups: &[],
+            roles: &[s!("no-such-role")],
+        };
+
+        assert_eq!(
+            access(&root, lead, path, 0, Some(&registry)),
+            CheckResult::Allow(0)
+        );
+        assert_eq!(
+            access(&root, junior, path, 0, Some(&registry)),
+            CheckResult::Deny
+        );
+        assert_eq!(
+            access(&root, nobody, path, 0, Some(&registry)),
+            CheckResult::Deny
+        );
+    }
+
+    #[test]
+    fn pattern_items_are_matched_most_specific_first() {
+        let mut root = Prefix::new();
+
+        let allow_everyone = |mask: Option<u64>| Object {
+            security: SecurityDescriptor {
+                acl: vec![Entry {
+                    subject: RuleSubject::Everyone,
+                    effect: Effect::Allow(mask),
+                }],
+            },
+        };
+
+        root.add_pattern_item("*", Item::Object(allow_everyone(Some(1))));
+        root.add_pattern_item("repo-*", Item::Object(allow_everyone(Some(2))));
+        root.add_item("repo-exact", Item::Object(allow_everyone(Some(4))));
+
+        let anyone = AccessToken {
+            name: "anyone",
+            groups: &[],
+            roles: &[],
+        };
+
+        // exact beats every pattern
+        assert_eq!(
+            access(&root, anyone, &["repo-exact"], 255, None),
+            CheckResult::Allow(4)
+        );
+        // the more specific pattern beats the bare wildcard
+        assert_eq!(
+            access(&root, anyone, &["repo-other"], 255, None),
+            CheckResult::Allow(2)
+        );
+        // only the bare wildcard matches unrelated names
+        assert_eq!(
+            access(&root, anyone, &["anything"], 255, None),
+            CheckResult::Allow(1)
+        );
+    }
+
+    #[test]
+    fn access_explained_reports_sudo_and_object_steps() {
+        let mut root = Prefix::new();
+
+        root.add_item(
+            SPECIAL_SEGMENT_SUDO,
+            Item::Object(Object {
+                security: SecurityDescriptor {
+                    acl: vec![Entry {
+                        subject: RuleSubject::User("jon_snow".to_string()),
+                        effect: Effect::Allow(Some(0)),
+                    }],
+                },
+            }),
+        );
+        root.add_item(
+            "GotFinal",
+            Item::Object(Object {
+                security: SecurityDescriptor::deny_all(),
+            }),
+        );
+
+        let jon_snow = AccessToken {
+            name: "jon_snow",
+            groups: &[],
+            roles: &[],
+        };
+        let path = &["GotFinal"];
+
+        let (result, trace) = access_explained(&root, jon_snow, path, 179, None);
+        assert_eq!(result, CheckResult::Allow(179));
+        assert_eq!(trace.len(), 2);
+        match &trace[0] {
+            TraceStep::PrefixCheck { segment, result, .. } => {
+                assert_eq!(segment, "GotFinal");
+                assert_eq!(*result, CheckResult::Allow(179));
+            }
+            other => panic!("expected PrefixCheck, got {:?}", other),
+        }
+        match &trace[1] {
+            TraceStep::SudoCheck { segment, granted } => {
+                assert_eq!(segment, "GotFinal");
+                assert!(*granted);
+            }
+            other => panic!("expected SudoCheck, got {:?}", other),
+        }
+
+        let cersei = AccessToken {
+            name: "cersei",
+            groups: &[],
+            roles: &[],
+        };
+        let (result, trace) = access_explained(&root, cersei, path, 179, None);
+        assert_eq!(result, CheckResult::Deny);
+        match trace.last() {
+            Some(TraceStep::ObjectCheck { matched, result }) => {
+                assert_eq!(*result, CheckResult::Deny);
+                assert_eq!(matched.len(), 1);
+                assert!(matches!(matched[0].effect, Effect::Deny));
+            }
+            other => panic!("expected ObjectCheck, got {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,177 @@
+//! On-disk persistence of a [`Prefix`] tree.
+//!
+//! [`Prefix::save`]/[`Prefix::load`] cover the common case of a tree backed
+//! by a single file; implement [`AclStore`] for anything else (a config
+//! service, a database row, ...).
+
+use crate::Prefix;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Failure to save or load a [`Prefix`] tree.
+#[derive(Debug)]
+pub enum PersistError {
+    Io(io::Error),
+    Serde(serde_json::Error),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PersistError::Io(e) => write!(f, "I/O error: {}", e),
+            PersistError::Serde(e) => write!(f, "(de)serialization error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PersistError {}
+
+impl From<io::Error> for PersistError {
+    fn from(e: io::Error) -> Self {
+        PersistError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for PersistError {
+    fn from(e: serde_json::Error) -> Self {
+        PersistError::Serde(e)
+    }
+}
+
+impl Prefix {
+    /// Serializes this tree to `path` as JSON, overwriting any existing file.
+    ///
+    /// Writes to a sibling temporary file first and renames it into place,
+    /// so a crash mid-write can't leave a truncated, unparsable ACL file
+    /// behind.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PersistError> {
+        let path = path.as_ref();
+        let tmp_path = path.with_extension("tmp");
+        let file = File::create(&tmp_path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Loads a tree previously written by [`Prefix::save`].
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Prefix, PersistError> {
+        let file = File::open(path)?;
+        let tree = serde_json::from_reader(file)?;
+        Ok(tree)
+    }
+}
+
+/// A pluggable storage backend for a [`Prefix`] tree, so downstream crates
+/// can supply their own config location (or storage medium entirely)
+/// instead of the crate hard-coding a path.
+pub trait AclStore {
+    type Error;
+
+    fn save(&self, tree: &Prefix) -> Result<(), Self::Error>;
+    fn load(&self) -> Result<Prefix, Self::Error>;
+}
+
+/// The default [`AclStore`]: a tree persisted as JSON at a fixed file path.
+#[derive(Debug, Clone)]
+pub struct FileAclStore {
+    path: PathBuf,
+}
+
+impl FileAclStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> FileAclStore {
+        FileAclStore { path: path.into() }
+    }
+}
+
+impl AclStore for FileAclStore {
+    type Error = PersistError;
+
+    fn save(&self, tree: &Prefix) -> Result<(), Self::Error> {
+        tree.save(&self.path)
+    }
+
+    fn load(&self) -> Result<Prefix, Self::Error> {
+        Prefix::load(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        access, AccessToken, Effect, Entry, Item, Object, RuleSubject, SecurityDescriptor,
+        SPECIAL_SEGMENT_SUDO,
+    };
+
+    #[test]
+    fn round_trips_through_json_file() {
+        let mut root = Prefix::new();
+        root.add_item(
+            SPECIAL_SEGMENT_SUDO,
+            Item::Object(Object {
+                security: SecurityDescriptor {
+                    acl: vec![Entry {
+                        subject: RuleSubject::User("jon_snow".to_string()),
+                        effect: Effect::Allow(Some(0)),
+                    }],
+                },
+            }),
+        );
+        root.add_item(
+            "GotFinal",
+            Item::Object(Object {
+                security: SecurityDescriptor::deny_all(),
+            }),
+        );
+
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("acl-persist-test-{:?}.json", std::thread::current().id()));
+
+        root.save(&path).unwrap();
+        let loaded = Prefix::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let jon_snow = AccessToken {
+            name: "jon_snow",
+            groups: &[],
+            roles: &[],
+        };
+        let path_segments = &["GotFinal"];
+        assert_eq!(
+            access(&loaded, jon_snow, path_segments, 179, None),
+            crate::CheckResult::Allow(179)
+        );
+    }
+
+    #[test]
+    fn file_store_round_trips() {
+        let mut root = Prefix::new();
+        root.add_item(
+            "doc",
+            Item::Object(Object {
+                security: SecurityDescriptor::allow_all(),
+            }),
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "acl-filestore-test-{:?}.json",
+            std::thread::current().id()
+        ));
+        let store = FileAclStore::new(path.clone());
+        store.save(&root).unwrap();
+        let loaded = store.load().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let anyone = AccessToken {
+            name: "anyone",
+            groups: &[],
+            roles: &[],
+        };
+        assert_eq!(
+            access(&loaded, anyone, &["doc"], 0, None),
+            crate::CheckResult::Allow(0)
+        );
+    }
+}